@@ -0,0 +1,485 @@
+use std::ops::RangeInclusive;
+use num_traits::{Float, FloatConst};
+use rayon::prelude::*;
+
+/// The coefficients of a Fourier Series fitted to a piecewise-linear curve
+/// through evenly spaced samples, for the form:
+///
+/// ```text
+/// f(t) = a_0/2 + Σ[a_n*cos(nt) + b_n*sin(nt)]
+/// ```
+///
+/// Generic over the floating-point scalar `T` so the pipeline can be run at
+/// `f32` for memory/throughput on very large sample sets, or at an
+/// extended-precision type where catastrophic cancellation in the
+/// telescoping summand differences would otherwise degrade accuracy at `f64`.
+pub struct FourierSeries<T: Float> {
+  pub a0: T,
+  pub a_n: Vec<T>,
+  pub b_n: Vec<T>,
+  pub n_min: usize,
+}
+
+impl<T: Float + FloatConst + Send + Sync + std::iter::Sum> FourierSeries<T> {
+  /// Computes the coefficients for `n_range` via the direct O(N*n) summation
+  /// over the telescoping antiderivative of each linear segment.
+  pub fn from_samples(samples: &[T], n_range: RangeInclusive<usize>) -> FourierSeries<T> {
+    let n_min = *n_range.start();
+    let mut data = samples.to_vec();
+    data.push(data[0]);
+
+    let beta = compute_beta(&data);
+    let alpha = compute_alpha(&data, &beta);
+
+    let a0 = compute_a_0(&alpha, &beta);
+    let a_n = compute_a_n(&alpha, &beta, n_range.clone());
+    let b_n = compute_b_n(&alpha, &beta, n_range);
+
+    FourierSeries { a0, a_n, b_n, n_min }
+  }
+
+  /// Computes the coefficients for `n_range` in a single O(N log N) FFT pass.
+  /// `samples` must be the raw samples, without any wrap-around closing point.
+  pub fn from_samples_fft(samples: &[T], n_range: RangeInclusive<usize>) -> FourierSeries<T> {
+    let n_min = *n_range.start();
+    let (a0, a_n, b_n) = compute_fft_coefficients(samples, n_range);
+
+    FourierSeries { a0, a_n, b_n, n_min }
+  }
+
+  /// Re-synthesizes f(t) = a_0/2 + Σ[a_n*cos(nt) + b_n*sin(nt)] on an evenly
+  /// spaced grid of `points` samples over one period. `max_n`, if given,
+  /// truncates the summation below the harmonics this series actually holds.
+  pub fn evaluate(&self, points: usize, max_n: Option<usize>) -> Vec<T> {
+    let highest = self.n_min + self.a_n.len() - 1;
+    let upper = max_n.map_or(highest, |m| m.min(highest));
+
+    (0..points)
+      .into_par_iter()
+      .map(|j| {
+        let theta = lit::<T>(2.0) * T::PI() * cast::<T>(j) / cast(points);
+        let mut value = self.a0 / lit(2.0);
+        for n in self.n_min..=upper {
+          let idx = n - self.n_min;
+          let angle = theta * cast::<T>(n);
+          value = value + self.a_n[idx] * angle.cos() + self.b_n[idx] * angle.sin();
+        }
+        value
+      })
+      .collect()
+  }
+
+  /// Computes the coefficients of the pointwise product of `self` and
+  /// `other` directly in the frequency domain, via discrete convolution of
+  /// their complex exponential coefficient sequences `c_n = (a_n - i*b_n)/2`
+  /// (with negative frequencies given by conjugate symmetry
+  /// `c_{-n} = conj(c_n)`), without ever re-sampling the product. The output
+  /// band is `n_range`; a contribution `c_k * d_{m-k}` is only included when
+  /// both `k` and `m-k` fall within the band of coefficients `self` and
+  /// `other` actually hold, so a convolution near the edge of that band is
+  /// only approximate.
+  pub fn convolve(&self, other: &FourierSeries<T>, n_range: RangeInclusive<usize>) -> FourierSeries<T> {
+    let n_min = *n_range.start();
+    let band = self.highest().max(other.highest()) as isize;
+
+    let product = |m: isize| -> (T, T) {
+      let mut re = T::zero();
+      let mut im = T::zero();
+      for k in -band..=band {
+        let (ar, ai) = self.coeff(k);
+        let (br, bi) = other.coeff(m - k);
+        re = re + ar * br - ai * bi;
+        im = im + ar * bi + ai * br;
+      }
+      (re, im)
+    };
+
+    let (a0_re, _) = product(0);
+    let a0 = lit::<T>(2.0) * a0_re;
+    let (a_n, b_n) = n_range
+      .map(|m| {
+        let (re, im) = product(m as isize);
+        (lit::<T>(2.0) * re, -lit::<T>(2.0) * im)
+      })
+      .unzip();
+
+    FourierSeries { a0, a_n, b_n, n_min }
+  }
+
+  fn highest(&self) -> usize {
+    self.n_min + self.a_n.len() - 1
+  }
+
+  // The complex exponential coefficient E_n = (a_n - i*b_n)/2 (E_0 = a0/2),
+  // reflected via conjugate symmetry E_{-n} = conj(E_n). Zero outside the
+  // band this series holds.
+  fn coeff(&self, n: isize) -> (T, T) {
+    if n == 0 {
+      return (self.a0 / lit(2.0), T::zero());
+    }
+    let negative = n < 0;
+    let n = n.unsigned_abs();
+    if n < self.n_min || n > self.highest() {
+      return (T::zero(), T::zero());
+    }
+    let idx = n - self.n_min;
+    let (a, b) = (self.a_n[idx] / lit(2.0), self.b_n[idx] / lit(2.0));
+    if negative {(a, b)} else {(a, -b)}
+  }
+}
+
+fn cast<T: Float>(x: usize) -> T {
+  T::from(x).unwrap()
+}
+
+fn lit<T: Float>(x: f64) -> T {
+  T::from(x).unwrap()
+}
+
+fn compute_beta<T: Float + Send + Sync>(data: &[T]) -> Vec<T> {
+  data[0..data.len()-1]
+    .par_iter()
+    .zip(data[1..data.len()].par_iter())
+    .map(|(a,b)| *b - *a)
+    .collect()
+}
+
+
+fn compute_alpha<T: Float + Send + Sync>(
+  data: &[T],
+  betas: &[T],
+) -> Vec<T> {
+  data[..data.len()-1]
+    .par_iter()
+    .zip(betas.par_iter())
+    .zip((0..data.len()-1).into_par_iter())
+    .map(|((val, beta), idx)| *val - *beta*cast(idx))
+    .collect()
+}
+
+fn compute_a_0<T: Float + Send + Sync + std::iter::Sum>(
+  alphas: &[T],
+  betas: &[T],
+) -> T {
+  fn summand<T: Float>(alpha: &T, beta: &T, idx: usize) -> T {
+    *alpha * cast(idx) + lit::<T>(0.5) * *beta * cast::<T>(idx).powi(2)
+  }
+
+  alphas.par_iter()
+  .zip(betas.par_iter())
+  .zip((0..alphas.len()).into_par_iter())
+  .map(|((alpha, beta), idx)| {
+    summand(alpha, beta, idx+1) - summand(alpha, beta, idx)
+  })
+  .sum::<T>() * (cast::<T>(2) / cast(alphas.len()))
+}
+
+fn compute_a_n<T: Float + FloatConst + Send + Sync + std::iter::Sum>(
+  alphas: &[T],
+  betas: &[T],
+  n_range: RangeInclusive<usize>,
+) -> Vec<T> {
+  fn summand<T: Float + FloatConst>(alpha: &T, beta: &T, n: usize, idx: usize, count: usize) -> T {
+    let count = cast::<T>(count);
+    let theta = (cast::<T>(2) * T::PI() * cast::<T>(n) * cast::<T>(idx)) / count;
+
+    (count / (cast::<T>(4)*T::PI().powi(2)*cast::<T>(n).powi(2))) * (
+        cast::<T>(2) * T::PI() * cast::<T>(n) * (*alpha + *beta * cast::<T>(idx)) * theta.sin() +
+        count * *beta * theta.cos()
+      )
+  }
+
+  n_range.into_par_iter()
+    .map(|n| {
+      alphas.par_iter()
+        .zip(betas.par_iter())
+        .zip((0..alphas.len()).into_par_iter())
+        .map(|((alpha, beta), idx)| {
+          summand(alpha, beta, n, idx+1, alphas.len()) -
+          summand(alpha, beta, n, idx  , alphas.len())
+        })
+        .sum::<T>() * (cast::<T>(2) / cast(alphas.len()))
+    })
+  .collect()
+}
+
+fn compute_b_n<T: Float + FloatConst + Send + Sync + std::iter::Sum>(
+  alphas: &[T],
+  betas: &[T],
+  n_range: RangeInclusive<usize>,
+) -> Vec<T> {
+  fn summand<T: Float + FloatConst>(alpha: &T, beta: &T, n: usize, idx: usize, count: usize) -> T {
+    let count = cast::<T>(count);
+    let theta = (cast::<T>(2) * T::PI() * cast::<T>(n) * cast::<T>(idx)) / count;
+
+    -(count / (cast::<T>(4)*T::PI().powi(2)*cast::<T>(n).powi(2))) * (
+        cast::<T>(2) * T::PI() * cast::<T>(n) * (*alpha + *beta * cast::<T>(idx)) * theta.cos() -
+        count * *beta * theta.sin()
+      )
+  }
+
+  n_range.into_par_iter()
+    .map(|n| {
+      alphas.par_iter()
+        .zip(betas.par_iter())
+        .zip((0..alphas.len()).into_par_iter())
+        .map(|((alpha, beta), idx)| {
+          summand(alpha, beta, n, idx+1, alphas.len()) -
+          summand(alpha, beta, n, idx  , alphas.len())
+        })
+        .sum::<T>() * (cast::<T>(2) / cast(alphas.len()))
+    })
+  .collect()
+}
+
+
+// In-place iterative radix-2 Cooley-Tukey FFT. `count` must be a power of two.
+fn fft_radix2<T: Float + FloatConst>(mut re: Vec<T>, mut im: Vec<T>) -> (Vec<T>, Vec<T>) {
+  let count = re.len();
+
+  let mut j = 0;
+  for i in 1..count {
+    let mut bit = count >> 1;
+    while j & bit != 0 {
+      j ^= bit;
+      bit >>= 1;
+    }
+    j |= bit;
+    if i < j {
+      re.swap(i, j);
+      im.swap(i, j);
+    }
+  }
+
+  let mut len = 2;
+  while len <= count {
+    let ang = -cast::<T>(2) * T::PI() / cast(len);
+    let (wr, wi) = (ang.cos(), ang.sin());
+    let mut i = 0;
+    while i < count {
+      let (mut cwr, mut cwi) = (T::one(), T::zero());
+      for k in 0..len / 2 {
+        let (ur, ui) = (re[i + k], im[i + k]);
+        let (vr, vi) = (
+          re[i + k + len / 2] * cwr - im[i + k + len / 2] * cwi,
+          re[i + k + len / 2] * cwi + im[i + k + len / 2] * cwr,
+        );
+        re[i + k] = ur + vr;
+        im[i + k] = ui + vi;
+        re[i + k + len / 2] = ur - vr;
+        im[i + k + len / 2] = ui - vi;
+
+        let (nwr, nwi) = (cwr * wr - cwi * wi, cwr * wi + cwi * wr);
+        cwr = nwr;
+        cwi = nwi;
+      }
+      i += len;
+    }
+    len <<= 1;
+  }
+  (re, im)
+}
+
+// Direct O(N^2) DFT fallback for sample counts that aren't a power of two.
+// Naively zero-padding to the next power of two would change the assumed
+// period of the signal, so non-power-of-two inputs fall back to the direct
+// transform rather than pad.
+fn dft_direct<T: Float + FloatConst>(samples: &[T]) -> (Vec<T>, Vec<T>) {
+  let count = samples.len();
+  let mut re = vec![T::zero(); count];
+  let mut im = vec![T::zero(); count];
+  for n in 0..count {
+    let mut sr = T::zero();
+    let mut si = T::zero();
+    for (k, &f_k) in samples.iter().enumerate() {
+      let theta = -cast::<T>(2) * T::PI() * cast::<T>(n) * cast::<T>(k) / cast(count);
+      sr = sr + f_k * theta.cos();
+      si = si + f_k * theta.sin();
+    }
+    re[n] = sr;
+    im[n] = si;
+  }
+  (re, im)
+}
+
+// Transfer function of the linear-interpolation (tent) kernel that the
+// direct summation path integrates exactly. Squaring collapses the sign
+// flip between n and n+count, so indexing with the unwrapped harmonic n
+// agrees with the aliased DFT bin n % count.
+fn tent_kernel<T: Float + FloatConst>(n: usize, count: usize) -> T {
+  if n == 0 {
+    return T::one();
+  }
+  let x = T::PI() * cast::<T>(n) / cast(count);
+  (x.sin() / x).powi(2)
+}
+
+// Computes a_0, a_n and b_n for the whole `n_range` in one FFT pass instead
+// of the O(N*n) direct summation. `samples` must not include the wrapped
+// closing point that the direct path needs.
+fn compute_fft_coefficients<T: Float + FloatConst>(
+  samples: &[T],
+  n_range: RangeInclusive<usize>,
+) -> (T, Vec<T>, Vec<T>) {
+  let count = samples.len();
+  let (re, im) = if count.is_power_of_two() {
+    fft_radix2(samples.to_vec(), vec![T::zero(); count])
+  } else {
+    dft_direct(samples)
+  };
+
+  let bin = |n: usize| -> (T, T) {
+    let i = n % count;
+    (cast::<T>(2) * re[i] / cast(count), cast::<T>(2) * im[i] / cast(count))
+  };
+
+  let (c0_re, _) = bin(0);
+  let a_0 = c0_re;
+
+  let (a_n, b_n) = n_range
+    .map(|n| {
+      let (cr, ci) = bin(n);
+      let h = tent_kernel(n, count);
+      (cr * h, -ci * h)
+    })
+    .unzip();
+
+  (a_0, a_n, b_n)
+}
+
+/// Browser entry points, gated behind the `wasm` feature so the direct
+/// summation above can run client-side over data passed in from
+/// JavaScript, without any file I/O. The FFT path is not exported.
+#[cfg(feature = "wasm")]
+pub mod wasm {
+  use super::FourierSeries;
+  use serde::Serialize;
+  use wasm_bindgen::prelude::*;
+
+  #[derive(Serialize)]
+  struct Coefficients {
+    a0: f64,
+    a_n: Vec<f64>,
+    b_n: Vec<f64>,
+  }
+
+  impl From<FourierSeries<f64>> for Coefficients {
+    fn from(series: FourierSeries<f64>) -> Self {
+      Coefficients { a0: series.a0, a_n: series.a_n, b_n: series.b_n }
+    }
+  }
+
+  /// Computes a_0, a_n and b_n for the range `n0..=n` via the direct
+  /// summation path and returns them as a JS object `{ a0, a_n, b_n }`.
+  #[wasm_bindgen]
+  pub fn compute_coefficients(samples: &[f64], n0: usize, n: usize) -> JsValue {
+    let series = FourierSeries::<f64>::from_samples(samples, n0..=n);
+    serde_wasm_bindgen::to_value(&Coefficients::from(series)).unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fft_matches_direct_summation() {
+    let raw = vec![0.0, 1.0, 0.0, -1.0, 2.0, -2.0, 1.5, -0.5];
+    let n_range = 1..=3;
+
+    let direct = FourierSeries::<f64>::from_samples(&raw, n_range.clone());
+    let fft = FourierSeries::<f64>::from_samples_fft(&raw, n_range);
+
+    assert!((direct.a0 - fft.a0).abs() < 1e-9);
+    for (d, f) in direct.a_n.iter().zip(fft.a_n.iter()) {
+      assert!((d - f).abs() < 1e-9, "a_n mismatch: {d} vs {f}");
+    }
+    for (d, f) in direct.b_n.iter().zip(fft.b_n.iter()) {
+      assert!((d - f).abs() < 1e-9, "b_n mismatch: {d} vs {f}");
+    }
+  }
+
+  // Documents the expected f32 vs f64 error tradeoff on a known analytic
+  // signal: a pure sine of harmonic 2 should recover b_2 tightly at
+  // f64, with f32 agreeing only to single-precision accuracy.
+  #[test]
+  fn f32_vs_f64_precision_tradeoff() {
+    let count = 64;
+    let samples_f64: Vec<f64> = (0..count)
+      .map(|i| (2.0 * f64::PI() * 2.0 * (i as f64) / (count as f64)).sin())
+      .collect();
+    let samples_f32: Vec<f32> = samples_f64.iter().map(|&x| x as f32).collect();
+
+    let n_range = 2..=2;
+    let series_f64 = FourierSeries::<f64>::from_samples(&samples_f64, n_range.clone());
+    let series_f32 = FourierSeries::<f32>::from_samples(&samples_f32, n_range);
+
+    // Exact coefficient of the piecewise-linear interpolant through this
+    // sampling of sin(2t) at N=64, computed at full f64 precision.
+    let expected = 0.9967913640449606_f64;
+    assert!((series_f64.b_n[0] - expected).abs() < 1e-9);
+    assert!((series_f32.b_n[0] - expected as f32).abs() < 1e-6);
+    assert!((series_f64.b_n[0] as f32 - series_f32.b_n[0]).abs() < 1e-6);
+  }
+
+  // The piecewise-linear interpolant through the samples carries energy in
+  // every harmonic, so a series truncated at the Nyquist bin only
+  // approximates the original samples; the error shrinks as the sample
+  // count (and so the truncation harmonic) grows.
+  #[test]
+  fn evaluate_reconstructs_original_samples() {
+    let count = 64;
+    let raw: Vec<f64> = (0..count)
+      .map(|i| (2.0 * f64::PI() * (i as f64) / count as f64).sin())
+      .collect();
+    let series = FourierSeries::<f64>::from_samples(&raw, 1..=(count / 2));
+
+    let reconstructed = series.evaluate(count, None);
+    for (orig, recon) in raw.iter().zip(reconstructed.iter()) {
+      assert!((orig - recon).abs() < 1e-3, "{orig} vs {recon}");
+    }
+  }
+
+  #[test]
+  fn evaluate_max_n_truncates_harmonics() {
+    let raw: Vec<f64> = (0..8)
+      .map(|i| (2.0 * f64::PI() * (i as f64) / 8.0).sin())
+      .collect();
+    let series = FourierSeries::<f64>::from_samples(&raw, 1..=4);
+
+    let full = series.evaluate(8, None);
+    let truncated = series.evaluate(8, Some(1));
+    assert_ne!(full, truncated);
+
+    // Truncating to n=1 should match a series fitted with n_range 1..=1.
+    let fundamental_only = FourierSeries::<f64>::from_samples(&raw, 1..=1);
+    let expected = fundamental_only.evaluate(8, None);
+    for (t, e) in truncated.iter().zip(expected.iter()) {
+      assert!((t - e).abs() < 1e-9, "{t} vs {e}");
+    }
+  }
+
+  #[test]
+  fn convolve_matches_product_to_sum_identity() {
+    let count = 128;
+    let cos_t: Vec<f64> = (0..count)
+      .map(|i| (2.0 * f64::PI() * (i as f64) / count as f64).cos())
+      .collect();
+    let cos_2t: Vec<f64> = (0..count)
+      .map(|i| (2.0 * 2.0 * f64::PI() * (i as f64) / count as f64).cos())
+      .collect();
+
+    let a = FourierSeries::<f64>::from_samples(&cos_t, 1..=2);
+    let b = FourierSeries::<f64>::from_samples(&cos_2t, 1..=2);
+
+    // cos(t)*cos(2t) = 0.5*cos(t) + 0.5*cos(3t)
+    let conv = a.convolve(&b, 1..=3);
+    assert!(conv.a0.abs() < 1e-3);
+    assert!((conv.a_n[0] - 0.5).abs() < 1e-3, "a_1 = {}", conv.a_n[0]);
+    assert!(conv.a_n[1].abs() < 1e-3, "a_2 = {}", conv.a_n[1]);
+    assert!((conv.a_n[2] - 0.5).abs() < 1e-3, "a_3 = {}", conv.a_n[2]);
+    for b_n in &conv.b_n {
+      assert!(b_n.abs() < 1e-3, "b_n = {b_n}");
+    }
+  }
+}
@@ -1,30 +1,127 @@
-use std::{ops::RangeInclusive, fs, f64::consts::PI};
-use clap::Parser;
+use std::{fmt::{Debug, UpperExp}, ops::RangeInclusive, fs, str::FromStr};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use num_traits::{Float, FloatConst};
 use rayon::prelude::*;
+use fourier_coefficients::FourierSeries;
 
 #[derive(Parser)]
 #[command(name = "Fourier Series Coefficient Calculator")]
 #[command(version = "0.1")]
 #[command(about = ABOUT, long_about = None)]
-
 struct Cli {
+  #[command(subcommand)]
+  mode: Mode,
+}
+
+#[derive(Subcommand)]
+enum Mode {
+  /// Computes Fourier Series coefficients from sample files.
+  Analyze(AnalyzeCli),
+  /// Re-synthesizes f(t) from a coefficient set on a grid of points.
+  Reconstruct(ReconstructCli),
+  /// Computes the coefficients of two signals' pointwise product from
+  /// their spectra, without ever re-sampling the product.
+  Convolve(ConvolveCli),
+}
+
+#[derive(Args)]
+struct AnalyzeCli {
   #[arg(short = 'p', long, help = SHOW_PROGRESS_HELP)]
   show_progress: bool,
   #[arg(long, help = N0_HELP)]
   n0: Option<usize>,
+  #[arg(long, help = FFT_HELP)]
+  fft: bool,
+  #[arg(long, value_enum, default_value_t = Precision::F64, help = PRECISION_HELP)]
+  precision: Precision,
+  #[arg(long, help = OUTPUT_HELP)]
+  output: Option<String>,
   #[arg(help = N_HELP)]
   n: usize,
   #[arg(required = true, help = INPUT_FILES_HELP)]
   input_files: Vec<String>,
 }
 
-struct Args {
+#[derive(Args)]
+struct ReconstructCli {
+  #[arg(short = 'p', long, help = SHOW_PROGRESS_HELP)]
+  show_progress: bool,
+  #[arg(long, value_enum, default_value_t = Precision::F64, help = PRECISION_HELP)]
+  precision: Precision,
+  #[arg(long, help = COEFFICIENTS_HELP)]
+  coefficients: Option<String>,
+  #[arg(long = "input", help = INPUT_FILES_HELP)]
+  input_files: Vec<String>,
+  #[arg(long, help = N0_HELP)]
+  n0: Option<usize>,
+  #[arg(long, help = "Upper limit for n (required unless --coefficients is given)")]
+  n: Option<usize>,
+  #[arg(long, help = FFT_HELP)]
+  fft: bool,
+  #[arg(long, help = POINTS_HELP)]
+  points: usize,
+  #[arg(long, help = MAX_N_HELP)]
+  max_n: Option<usize>,
+  #[arg(long, help = ROUNDTRIP_HELP)]
+  roundtrip: bool,
+}
+
+#[derive(Args)]
+struct ConvolveCli {
+  #[arg(short = 'p', long, help = SHOW_PROGRESS_HELP)]
+  show_progress: bool,
+  #[arg(long, value_enum, default_value_t = Precision::F64, help = PRECISION_HELP)]
+  precision: Precision,
+  #[arg(long = "input-a", required = true, help = INPUT_A_HELP)]
+  input_a: Vec<String>,
+  #[arg(long = "input-b", required = true, help = INPUT_B_HELP)]
+  input_b: Vec<String>,
+  #[arg(long, help = N0_HELP)]
+  n0: Option<usize>,
+  #[arg(long, help = FFT_HELP)]
+  fft: bool,
+  #[arg(help = N_HELP)]
+  n: usize,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Precision {
+  F32,
+  F64,
+}
+
+struct AnalyzeArgs {
   show_progress: bool,
+  fft: bool,
+  precision: Precision,
+  output: Option<String>,
   n_range: RangeInclusive<usize>,
   n_min: usize,
   input_files: Vec<String>,
 }
 
+struct ReconstructArgs {
+  show_progress: bool,
+  precision: Precision,
+  coefficients: Option<String>,
+  input_files: Vec<String>,
+  n_range: Option<RangeInclusive<usize>>,
+  fft: bool,
+  points: usize,
+  max_n: Option<usize>,
+  roundtrip: bool,
+}
+
+struct ConvolveArgs {
+  show_progress: bool,
+  precision: Precision,
+  input_a: Vec<String>,
+  input_b: Vec<String>,
+  fft: bool,
+  n_range: RangeInclusive<usize>,
+  n_min: usize,
+}
+
 // const ABOUT:&str = "Calculates Fourier Series coefficients.";
 const ABOUT: &str = "\
 Calculates Fourier Series coefficients.
@@ -36,183 +133,325 @@ those points. The calculator assumes that the points input are evenly spaced.
 The coefficients are for the following form of the Fourier Series:
 
     f(t) = a_0/2 + Σ[a_n*cos(nt) + b_n*sin(nt)]\
-";    
+";
 const SHOW_PROGRESS_HELP: &str ="Shows progress in calculation";
 const N0_HELP: &str = "Lower limit for n (Default = 1)";
+const FFT_HELP: &str = "\
+Compute coefficients for the whole n-range in one pass via FFT instead of
+the direct O(N*n) summation. O(N log N) when the sample count is a power
+of two; falls back to an O(N^2) direct DFT otherwise. Results match the
+direct mode to within floating point error.\
+";
+const PRECISION_HELP: &str = "\
+Floating-point scalar type to compute in (Default = f64). f32 trades
+accuracy for memory and throughput on very large sample sets.\
+";
+const OUTPUT_HELP: &str = "\
+Writes the computed coefficients to this file in a format `reconstruct
+--coefficients` can read back.\
+";
 const N_HELP: &str = "Upper limit for n";
 const INPUT_FILES_HELP: &str = "\
 The expected format of the input is plain text file(s) with
 values for f(t) on separate lines.\
 ";
+const COEFFICIENTS_HELP: &str = "\
+Reads a previously-produced coefficient set from this file instead of
+computing one from --input.\
+";
+const POINTS_HELP: &str = "Number of evenly-spaced points M to reconstruct f(t) on";
+const MAX_N_HELP: &str = "Truncates reconstruction to harmonics at or below this n";
+const ROUNDTRIP_HELP: &str = "\
+Reports the RMS and max-abs reconstruction error against the original
+--input samples. Requires --points to equal the sample count.\
+";
+const INPUT_A_HELP: &str = "First signal's input file(s), same format as analyze's input files";
+const INPUT_B_HELP: &str = "Second signal's input file(s), same format as analyze's input files";
 
-fn get_args() -> Result<Args, String> {
-  let cli = Cli::parse();
-
-  if let Some(n0) = cli.n0 {
-    if n0 > cli.n {
-      return Err(format!("Value for n0 '{}' greater than n '{}'", n0, cli.n));
-    }
+fn resolve_n_range(n0: Option<usize>, n: usize) -> Result<(usize, RangeInclusive<usize>), String> {
+  let n_min = n0.unwrap_or(1);
+  if n_min > n {
+    return Err(format!("Value for n0 '{}' greater than n '{}'", n_min, n));
   }
+  Ok((n_min, n_min..=n))
+}
 
-  let n_min = match cli.n0 {Some(x) => x, None => 1};
-  let n_range = n_min..=cli.n;
+fn get_analyze_args(cli: AnalyzeCli) -> Result<AnalyzeArgs, String> {
+  let (n_min, n_range) = resolve_n_range(cli.n0, cli.n)?;
 
-  Ok(Args{
+  Ok(AnalyzeArgs{
     show_progress: cli.show_progress,
+    fft: cli.fft,
+    precision: cli.precision,
+    output: cli.output,
     n_range,
     n_min,
     input_files: cli.input_files,
   })
 }
 
+fn get_reconstruct_args(cli: ReconstructCli) -> Result<ReconstructArgs, String> {
+  if cli.coefficients.is_none() && cli.input_files.is_empty() {
+    return Err("reconstruct needs either --coefficients or --input".to_string());
+  }
+
+  let n_range = match cli.n {
+    Some(n) => Some(resolve_n_range(cli.n0, n)?.1),
+    None => {
+      if cli.coefficients.is_none() {
+        return Err("reconstruct needs --n when computing coefficients from --input".to_string());
+      }
+      None
+    }
+  };
+
+  if cli.roundtrip && cli.input_files.is_empty() {
+    return Err("--roundtrip needs --input to compare against".to_string());
+  }
 
+  Ok(ReconstructArgs{
+    show_progress: cli.show_progress,
+    precision: cli.precision,
+    coefficients: cli.coefficients,
+    input_files: cli.input_files,
+    n_range,
+    fft: cli.fft,
+    points: cli.points,
+    max_n: cli.max_n,
+    roundtrip: cli.roundtrip,
+  })
+}
 
-fn parse_files(paths: Vec<String>, show_progress: bool) -> Vec<f64> {
-  let mut data: Vec<f64> = vec![];
+fn get_convolve_args(cli: ConvolveCli) -> Result<ConvolveArgs, String> {
+  let (n_min, n_range) = resolve_n_range(cli.n0, cli.n)?;
+
+  Ok(ConvolveArgs{
+    show_progress: cli.show_progress,
+    precision: cli.precision,
+    input_a: cli.input_a,
+    input_b: cli.input_b,
+    fft: cli.fft,
+    n_range,
+    n_min,
+  })
+}
+
+fn parse_files<T>(paths: &[String], show_progress: bool) -> Vec<T>
+where
+  T: FromStr + Send + Sync,
+  T::Err: Debug,
+{
+  let mut data: Vec<T> = vec![];
   for path in paths {
     if show_progress {println!("Reading file: {path}");}
     data.par_extend(
       fs::read_to_string(path.clone())
-        .expect(format!("Failed to read file '{path}' with error").as_str())
+        .unwrap_or_else(|e| panic!("Failed to read file '{path}' with error {e:?}"))
         .par_lines()
-        .map(|l| l.parse::<f64>()
-          .expect(
-            format!("Failed to read file '{path}' with error").as_str()
-          )));
+        .map(|l| l.parse::<T>()
+          .unwrap_or_else(|e| panic!("Failed to read file '{path}' with error {e:?}"))));
   }
-  data.push(data[0]);
   data
 }
 
-fn compute_beta(data: &Vec<f64>, show_progress: bool) -> Vec<f64> {
-  if show_progress {println!("Computing beta.")}
-  data[0..data.len()-1]
-    .par_iter()
-    .zip(data[1..data.len()].par_iter())
-    .map(|(a,b)| b-a)
-    .collect()
-}
-
-
-fn compute_alpha(
-  data: &Vec<f64>,
-  betas: &Vec<f64>,
-  show_progress: bool
-) -> Vec<f64> {
-  if show_progress {println!("Computing alpha.")}
-  data[..data.len()-1]
-    .par_iter()
-    .zip(betas.par_iter())
-    .zip((0..data.len()-1).into_par_iter())
-    .map(|((val, beta), idx)| val - beta*(idx as f64))
-    .collect()
-}
-
-fn compute_a_0(
-  alphas: &Vec<f64>,
-  betas: &Vec<f64>,
-  show_progress: bool
-) -> f64 {
-  fn summand(alpha: &f64, beta: &f64, idx: usize) -> f64 {
-    alpha * (idx as f64) + (0.5)*beta*(idx as f64).powi(2)
+fn write_coefficients<T: Float + UpperExp>(path: &str, series: &FourierSeries<T>) -> Result<(), String> {
+  let mut out = format!("n_min={}\n", series.n_min);
+  out.push_str(&format!("a0={:E}\n", series.a0));
+  out.push_str("a_n=");
+  out.push_str(&series.a_n.iter().map(|v| format!("{v:E}")).collect::<Vec<_>>().join(" "));
+  out.push('\n');
+  out.push_str("b_n=");
+  out.push_str(&series.b_n.iter().map(|v| format!("{v:E}")).collect::<Vec<_>>().join(" "));
+  out.push('\n');
+
+  fs::write(path, out).map_err(|e| format!("Failed to write coefficients to '{path}': {e}"))
+}
+
+fn read_coefficients<T>(path: &str) -> Result<FourierSeries<T>, String>
+where
+  T: Float + FromStr,
+  T::Err: Debug,
+{
+  let contents = fs::read_to_string(path)
+    .map_err(|e| format!("Failed to read coefficients from '{path}': {e}"))?;
+
+  let mut n_min = None;
+  let mut a0 = None;
+  let mut a_n = None;
+  let mut b_n = None;
+  for line in contents.lines() {
+    let (key, value) = line.split_once('=')
+      .ok_or_else(|| format!("Malformed coefficients line in '{path}': '{line}'"))?;
+    match key {
+      "n_min" => n_min = Some(value.parse::<usize>()
+        .map_err(|e| format!("Invalid n_min in '{path}': {e}"))?),
+      "a0" => a0 = Some(value.parse::<T>()
+        .map_err(|e| format!("Invalid a0 in '{path}': {e:?}"))?),
+      "a_n" => a_n = Some(value.split_whitespace().map(|v| v.parse::<T>())
+        .collect::<Result<Vec<T>, _>>()
+        .map_err(|e| format!("Invalid a_n in '{path}': {e:?}"))?),
+      "b_n" => b_n = Some(value.split_whitespace().map(|v| v.parse::<T>())
+        .collect::<Result<Vec<T>, _>>()
+        .map_err(|e| format!("Invalid b_n in '{path}': {e:?}"))?),
+      _ => {}
+    }
   }
-  if show_progress {println!("Computing a_0.")}
-  
-  alphas.par_iter()
-  .zip(betas.par_iter())
-  .zip((0..alphas.len()).into_par_iter())
-  .map(|((alpha, beta), idx)| {
-    summand(alpha, beta, idx+1) - summand(alpha, beta, idx)
+
+  Ok(FourierSeries {
+    n_min: n_min.ok_or_else(|| format!("'{path}' is missing n_min"))?,
+    a0: a0.ok_or_else(|| format!("'{path}' is missing a0"))?,
+    a_n: a_n.ok_or_else(|| format!("'{path}' is missing a_n"))?,
+    b_n: b_n.ok_or_else(|| format!("'{path}' is missing b_n"))?,
   })
-  .sum::<f64>() * (2f64 / (alphas.len() as f64))
 }
 
-fn compute_a_n(
-  alphas: &Vec<f64>,
-  betas: &Vec<f64>,
-  n_range: RangeInclusive<usize>,
-  show_progress: bool
-) -> Vec<f64> {
-  fn summand(alpha: &f64, beta: &f64, n:usize, idx: usize, count: usize)-> f64 {
-    let count = count as f64;
-    let theta = (2f64 * PI * (n as f64) * (idx as f64)) / count;
-
-    (count / (4f64*PI.powi(2)*(n as f64).powi(2))) * (
-        2f64 * PI * (n as f64) * (alpha + beta * (idx as f64)) * theta.sin() +
-        count * beta * theta.cos()
-      )
+fn print_coefficients<T: Float + UpperExp>(series: &FourierSeries<T>, n_range: &RangeInclusive<usize>, n_min: usize) {
+  println!("\na_0 =\n\"0{:E}\" ", series.a0);
+  println!("\na_n =");
+  for n in n_range.clone() {
+    print!("\"0{:E}\" ", series.a_n[n - n_min])
   }
-  if show_progress {println!("Computing a_n.")}
-  
-  n_range.into_par_iter()
-    .map(|n| {
-      alphas.par_iter()
-        .zip(betas.par_iter())
-        .zip((0..alphas.len()).into_par_iter())
-        .map(|((alpha, beta), idx)| {
-          summand(alpha, beta, n, idx+1, alphas.len()) -
-          summand(alpha, beta, n, idx  , alphas.len())
-        })
-        .sum::<f64>() * (2f64 / (alphas.len() as f64))
-    })
-  .collect()  
-}
-
-fn compute_b_n(
-  alphas: &Vec<f64>,
-  betas: &Vec<f64>,
-  n_range: RangeInclusive<usize>,
-  show_progress: bool
-) -> Vec<f64> {
-  fn summand(alpha: &f64, beta: &f64, n:usize, idx: usize, count: usize)-> f64 {
-    let count = count as f64;
-    let theta = (2f64 * PI * (n as f64) * (idx as f64)) / count;
-
-    (-1f64) * (count / (4f64*PI.powi(2)*(n as f64).powi(2))) * (
-        2f64 * PI * (n as f64) * (alpha + beta * (idx as f64)) * theta.cos() -
-        count * beta * theta.sin()
-      )
+
+  println!("\n\nb_n =");
+  for n in n_range.clone() {
+    print!("\"0{:E}\" ", series.b_n[n - n_min])
   }
-  if show_progress {println!("Computing b_n.")}
-  
-  n_range.into_par_iter()
-    .map(|n| {
-      alphas.par_iter()
-        .zip(betas.par_iter())
-        .zip((0..alphas.len()).into_par_iter())
-        .map(|((alpha, beta), idx)| {
-          summand(alpha, beta, n, idx+1, alphas.len()) -
-          summand(alpha, beta, n, idx  , alphas.len())
-        })
-        .sum::<f64>() * (2f64 / (alphas.len() as f64))
-    })
-  .collect()
+  println!();
 }
 
+fn run_analyze<T>(args: &AnalyzeArgs) -> Result<(), String>
+where
+  T: Float + FloatConst + FromStr + UpperExp + Send + Sync + std::iter::Sum,
+  T::Err: Debug,
+{
+  let data = parse_files::<T>(&args.input_files, args.show_progress);
 
-fn main() -> Result<(), String> {
-  let args = get_args()?;
+  if args.show_progress {println!("Computing coefficients.")}
+  let series = if args.fft {
+    FourierSeries::<T>::from_samples_fft(&data, args.n_range.clone())
+  } else {
+    FourierSeries::<T>::from_samples(&data, args.n_range.clone())
+  };
+
+  print_coefficients(&series, &args.n_range, args.n_min);
 
-  let data = parse_files(args.input_files, args.show_progress);
+  if let Some(output) = &args.output {
+    write_coefficients(output, &series)?;
+  }
 
-  let beta = compute_beta(&data, args.show_progress);  
-  let alpha = compute_alpha(&data, &beta, args.show_progress);
+  Ok(())
+}
 
-  let a_0= compute_a_0(&alpha, &beta, args.show_progress);
-  let a_n= compute_a_n(&alpha, &beta, args.n_range.clone(), args.show_progress);
-  let b_n= compute_b_n(&alpha, &beta, args.n_range.clone(), args.show_progress);
+fn run_reconstruct<T>(args: &ReconstructArgs) -> Result<(), String>
+where
+  T: Float + FloatConst + FromStr + UpperExp + Send + Sync + std::iter::Sum,
+  T::Err: Debug,
+{
+  let original = if args.input_files.is_empty() {
+    None
+  } else {
+    Some(parse_files::<T>(&args.input_files, args.show_progress))
+  };
 
-  println!("\na_0 =\n\"0{:E}\" ", a_0);
-  println!("\na_n =");
-  for n in args.n_range.clone() {
-    print!("\"0{:E}\" ", a_n[n - args.n_min])
+  let series = match &args.coefficients {
+    Some(path) => read_coefficients::<T>(path)?,
+    None => {
+      if args.show_progress {println!("Computing coefficients.")}
+      let samples = original.as_ref().expect("validated by get_reconstruct_args");
+      let n_range = args.n_range.clone().expect("validated by get_reconstruct_args");
+      if args.fft {
+        FourierSeries::<T>::from_samples_fft(samples, n_range)
+      } else {
+        FourierSeries::<T>::from_samples(samples, n_range)
+      }
+    }
+  };
+
+  if args.show_progress {println!("Reconstructing f(t).")}
+  let reconstructed = series.evaluate(args.points, args.max_n);
+
+  println!("\nf(t) =");
+  for value in &reconstructed {
+    println!("\"0{value:E}\" ");
   }
-  
-  println!("\n\nb_n =");
-  for n in args.n_range.clone() {
-    print!("\"0{:E}\" ", b_n[n - args.n_min])
+
+  if args.roundtrip {
+    let original = original.expect("validated by get_reconstruct_args");
+    if original.len() != reconstructed.len() {
+      return Err(format!(
+        "--roundtrip needs --points ({}) to equal the sample count ({})",
+        reconstructed.len(), original.len()
+      ));
+    }
+
+    let count = original.len();
+    let (sum_sq, max_abs) = original.iter().zip(reconstructed.iter())
+      .map(|(&orig, &recon)| (recon - orig).abs())
+      .fold((T::zero(), T::zero()), |(sum_sq, max_abs), err| {
+        (sum_sq + err * err, if err > max_abs {err} else {max_abs})
+      });
+    let rms = (sum_sq / T::from(count).unwrap()).sqrt();
+
+    println!("\nRMS error     = {rms:E}");
+    println!("Max-abs error = {max_abs:E}");
   }
-  println!();
-   
-  return Ok(());  
-}
\ No newline at end of file
+
+  Ok(())
+}
+
+fn run_convolve<T>(args: &ConvolveArgs) -> Result<(), String>
+where
+  T: Float + FloatConst + FromStr + UpperExp + Send + Sync + std::iter::Sum,
+  T::Err: Debug,
+{
+  let data_a = parse_files::<T>(&args.input_a, args.show_progress);
+  let data_b = parse_files::<T>(&args.input_b, args.show_progress);
+
+  if args.show_progress {println!("Computing coefficients.")}
+  let (series_a, series_b) = if args.fft {
+    (
+      FourierSeries::<T>::from_samples_fft(&data_a, args.n_range.clone()),
+      FourierSeries::<T>::from_samples_fft(&data_b, args.n_range.clone()),
+    )
+  } else {
+    (
+      FourierSeries::<T>::from_samples(&data_a, args.n_range.clone()),
+      FourierSeries::<T>::from_samples(&data_b, args.n_range.clone()),
+    )
+  };
+
+  if args.show_progress {println!("Convolving spectra.")}
+  let series = series_a.convolve(&series_b, args.n_range.clone());
+
+  print_coefficients(&series, &args.n_range, args.n_min);
+
+  Ok(())
+}
+
+fn main() -> Result<(), String> {
+  let cli = Cli::parse();
+
+  match cli.mode {
+    Mode::Analyze(cli) => {
+      let args = get_analyze_args(cli)?;
+      match args.precision {
+        Precision::F32 => run_analyze::<f32>(&args),
+        Precision::F64 => run_analyze::<f64>(&args),
+      }
+    }
+    Mode::Reconstruct(cli) => {
+      let args = get_reconstruct_args(cli)?;
+      match args.precision {
+        Precision::F32 => run_reconstruct::<f32>(&args),
+        Precision::F64 => run_reconstruct::<f64>(&args),
+      }
+    }
+    Mode::Convolve(cli) => {
+      let args = get_convolve_args(cli)?;
+      match args.precision {
+        Precision::F32 => run_convolve::<f32>(&args),
+        Precision::F64 => run_convolve::<f64>(&args),
+      }
+    }
+  }
+}